@@ -0,0 +1,136 @@
+//! A richer error type for handlers, so a failure can carry the HTTP status
+//! (and optional headers/body) it's meant to produce instead of forcing the
+//! server to fall back to a blanket 500 for everything.
+//!
+//! `HandlerError` implements `std::error::Error`, so it converts into the
+//! `Box<dyn Error + Send + Sync>` that `ModuleHandlers::request_handler` and
+//! `response_modifying_handler` already return via the usual `?` operator —
+//! no change to those signatures is required. [`MapHandlerError`] adds the
+//! `.with_status(...)` ergonomics Gotham-style frameworks offer, on top of
+//! any error or `Result`.
+//!
+//! This crate is scaffolding, not the fix on its own: nothing downcasts the
+//! boxed error back to a `HandlerError` once it leaves a handler, so the
+//! status/headers/body it carries aren't actually rendered yet — the server
+//! still falls back to its usual blanket error response for every handler
+//! error, same as before this type existed. Making the status stick would
+//! need `ferron_common`'s server-side error handling to downcast
+//! `Box<dyn Error + Send + Sync>` to `HandlerError` (e.g. via
+//! `Error::downcast_ref`) before rendering, which is out of this repo's scope.
+
+use std::error::Error;
+use std::fmt;
+
+use bytes::Bytes;
+use hyper::header::{HeaderName, HeaderValue};
+use hyper::{HeaderMap, StatusCode};
+
+/// An error carrying the HTTP response a handler wants the server to send
+/// back, alongside the underlying cause for logging.
+#[derive(Debug)]
+pub struct HandlerError {
+  source: Box<dyn Error + Send + Sync>,
+  status: Option<StatusCode>,
+  headers: Option<HeaderMap>,
+  body: Option<Bytes>,
+}
+
+impl HandlerError {
+  /// Wraps `source` with no particular status, headers, or body — callers
+  /// typically reach this via `.with_status(...)` rather than constructing
+  /// it directly.
+  pub fn new(source: impl Into<Box<dyn Error + Send + Sync>>) -> Self {
+    Self {
+      source: source.into(),
+      status: None,
+      headers: None,
+      body: None,
+    }
+  }
+
+  /// Sets the status this error should render as.
+  pub fn with_status(mut self, status: StatusCode) -> Self {
+    self.status = Some(status);
+    self
+  }
+
+  /// Adds a response header to send alongside the error status.
+  pub fn with_header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+    self.headers.get_or_insert_with(HeaderMap::new).insert(name, value);
+    self
+  }
+
+  /// Sets the response body to send instead of a generic error page.
+  pub fn with_body(mut self, body: impl Into<Bytes>) -> Self {
+    self.body = Some(body.into());
+    self
+  }
+
+  /// The status the server should render for this error, defaulting to
+  /// `500 Internal Server Error` if none was set.
+  pub fn status(&self) -> StatusCode {
+    self.status.unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+  }
+
+  /// Extra response headers to send, if any were set.
+  pub fn headers(&self) -> Option<&HeaderMap> {
+    self.headers.as_ref()
+  }
+
+  /// A custom response body to send instead of the server's default error
+  /// page, if one was set.
+  pub fn body(&self) -> Option<&Bytes> {
+    self.body.as_ref()
+  }
+}
+
+impl fmt::Display for HandlerError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{} ({})", self.source, self.status())
+  }
+}
+
+impl Error for HandlerError {
+  fn source(&self) -> Option<&(dyn Error + 'static)> {
+    Some(self.source.as_ref())
+  }
+}
+
+/// Lets any error (or a `Result` wrapping one) be annotated with the status
+/// it should render as, turning it into a [`HandlerError`] in the process.
+///
+/// `Kind` is [`ForError`] or [`ForResult`]; it only keeps the two blanket
+/// impls below from overlapping and is inferred at the call site.
+pub trait MapHandlerError<Kind> {
+  type Output;
+
+  fn with_status(self, status: StatusCode) -> Self::Output;
+}
+
+/// Selects the [`MapHandlerError`] impl for a bare error.
+pub enum ForError {}
+
+/// Selects the [`MapHandlerError`] impl for a `Result`.
+pub enum ForResult {}
+
+impl<E> MapHandlerError<ForError> for E
+where
+  E: Error + Send + Sync + 'static,
+{
+  type Output = HandlerError;
+
+  fn with_status(self, status: StatusCode) -> HandlerError {
+    HandlerError::new(self).with_status(status)
+  }
+}
+
+impl<T, E> MapHandlerError<ForResult> for Result<T, E>
+where
+  E: Error + Send + Sync + 'static,
+{
+  type Output = Result<T, HandlerError>;
+
+  fn with_status(self, status: StatusCode) -> Result<T, HandlerError> {
+    self.map_err(|e| HandlerError::new(e).with_status(status))
+  }
+}
@@ -7,7 +7,7 @@ use std::sync::Arc;
 use async_trait::async_trait;
 use bytes::Bytes;
 use http_body_util::combinators::BoxBody;
-use http_body_util::{BodyExt, Full};
+use http_body_util::{BodyExt, Empty, Full};
 use hyper::{Request, Response};
 
 use ferron_common::config::ServerConfiguration;
@@ -16,6 +16,38 @@ use ferron_common::logging::ErrorLogger;
 use ferron_common::modules::{Module, ModuleHandlers, ModuleLoader, ResponseData, SocketData};
 use ferron_common::util::ModuleCache;
 
+mod error;
+mod outbound;
+mod reload;
+mod template;
+pub mod testing;
+
+// `router` is declared `pub mod` so other crates in this workspace can reuse
+// it. That's a stopgap, not the fix the backlog asked for: the request was
+// for `ModuleRouter` to live in `ferron_common` so every module shares one
+// implementation instead of each vendoring its own copy. Landing that is out
+// of this repo's scope — this crate doesn't own `ferron_common` — so until
+// that move happens, this is still this one module's router that happens to
+// be `pub`, not the shared facility the ticket described.
+pub mod router;
+
+// Likewise `middleware` is `pub mod` so another module crate could reuse the
+// `ModuleLayer`/`Next`/`LayerStack` types. That still isn't the mechanism the
+// request asked for, though: `Module::get_module_handlers` in `ferron_common`
+// is untouched, so the server itself has no concept of per-module layers —
+// it only ever calls a module's `request_handler` directly. What's here is
+// `ExampleModule` composing its own layers in front of its own terminal
+// handler; the server-level composition the ticket describes would need a
+// `ferron_common` change this repo can't make.
+pub mod middleware;
+
+use error::MapHandlerError;
+use middleware::{LayerStack, Logger, ModuleLayer, Next};
+use outbound::{HostPolicy, OutboundHttp};
+use reload::VersionedEntry;
+use router::{MethodFilter, ModuleRouter, RouteMatch};
+use template::{Context, TemplateEngine};
+
 /// An example module loader that demonstrates how to create and manage modules in Ferron.
 ///
 /// The module loader is responsible for:
@@ -25,6 +57,10 @@ use ferron_common::util::ModuleCache;
 pub struct ExampleModuleLoader {
   /// Module cache that stores instances of ExampleModule indexed by configuration parameters
   cache: ModuleCache<ExampleModule>,
+  /// The route table shared by every cached `ExampleModule`, regardless of
+  /// which configuration it was built for. Reload swaps this one entry
+  /// instead of rebuilding every cached module.
+  routes: Arc<VersionedEntry<ModuleRouter<Route>>>,
 }
 
 impl Default for ExampleModuleLoader {
@@ -42,8 +78,64 @@ impl ExampleModuleLoader {
     Self {
       // Initialize with an empty vector since this module doesn't depend on specific properties
       cache: ModuleCache::new(vec![]),
+      routes: Arc::new(VersionedEntry::new(build_routes())),
     }
   }
+
+  /// Reacts to the server detecting a changed configuration file.
+  ///
+  /// Rebuilds the route table and swaps it into the shared
+  /// `VersionedEntry`: every cached `ExampleModule` holds an `Arc` to that
+  /// same entry, so this one swap is enough to update all of them. New
+  /// requests see the rebuilt table; requests already in flight keep
+  /// running against the `Arc` they already hold until they finish.
+  ///
+  /// This mirrors the `ModuleLoader::on_config_changed` hook ferron_common
+  /// is expected to grow, exposed here as an inherent method since this
+  /// crate only owns the example module's side of that contract. Takes
+  /// `&self`, not `&mut self`, because the actual swap happens inside the
+  /// shared `VersionedEntry` — the same entry [`Self::spawn_reload_watcher`]
+  /// below clones into a background task and swaps directly, without needing
+  /// exclusive access to the loader.
+  ///
+  /// Both config parameters are ignored: this example's route table
+  /// (`build_routes`) is hardcoded and never actually varies by
+  /// configuration, so every call here rebuilds and swaps in an identical
+  /// table. That demonstrates the reload *mechanism* — the swap really does
+  /// happen, and in-flight requests really do keep their old `Arc` — but
+  /// nothing in this crate shows the table's *contents* changing in
+  /// response to a config change; a real module would read `new_config`
+  /// here and register different routes from it.
+  pub async fn on_config_changed(&self, _new_config: &ServerConfiguration, _old_config: &ServerConfiguration) {
+    self.routes.invalidate(build_routes());
+  }
+
+  /// Spawns a background task that, every time `changes` reports a new
+  /// configuration, rebuilds the route table and swaps it into the shared
+  /// `VersionedEntry` — the same swap [`Self::on_config_changed`] makes — so
+  /// reload happens on its own instead of requiring something to call
+  /// `on_config_changed` by hand. The task holds its own `Arc` to the entry,
+  /// not a reference to the loader, and does not call `on_config_changed`.
+  ///
+  /// This is the watch-channel-driven half of hot reload, with two caveats.
+  /// First, `ferron_common` doesn't define a
+  /// `watch::Sender<ServerConfiguration>` that the real server publishes
+  /// config changes into, so nothing in this crate constructs and drives the
+  /// `Sender` half. Second, every change rebuilds the same hardcoded route
+  /// table (`build_routes`) — a reload happens on every signal, but its
+  /// result does not depend on the new configuration's contents.
+  pub fn spawn_reload_watcher(
+    &self,
+    runtime: &tokio::runtime::Handle,
+    mut changes: tokio::sync::watch::Receiver<ServerConfiguration>,
+  ) -> tokio::task::JoinHandle<()> {
+    let routes = self.routes.clone();
+    runtime.spawn(async move {
+      while changes.changed().await.is_ok() {
+        routes.invalidate(build_routes());
+      }
+    })
+  }
 }
 
 impl ModuleLoader for ExampleModuleLoader {
@@ -52,7 +144,7 @@ impl ModuleLoader for ExampleModuleLoader {
   /// # Parameters
   /// * `config` - The server configuration for this specific module instance
   /// * `_global_config` - Optional global server configuration (unused in this example)
-  /// * `_secondary_runtime` - A reference to the secondary Tokio runtime for asynchronous operations (unused in this example)
+  /// * `secondary_runtime` - The secondary Tokio runtime outbound requests are dispatched on
   ///
   /// # Returns
   /// A thread-safe, reference-counted module instance that implements the Module trait
@@ -60,13 +152,16 @@ impl ModuleLoader for ExampleModuleLoader {
     &mut self,
     config: &ServerConfiguration,
     _global_config: Option<&ServerConfiguration>,
-    _secondary_runtime: &tokio::runtime::Runtime,
+    secondary_runtime: &tokio::runtime::Runtime,
   ) -> Result<Arc<dyn Module + Send + Sync>, Box<dyn Error + Send + Sync>> {
     // Either get an existing module from cache or create a new one
+    let routes = self.routes.clone();
     Ok(
       self
         .cache
-        .get_or_init::<_, Box<dyn std::error::Error + Send + Sync>>(config, move |_| Ok(Arc::new(ExampleModule)))?,
+        .get_or_init::<_, Box<dyn std::error::Error + Send + Sync>>(config, move |_| {
+          Ok(Arc::new(ExampleModule::new(secondary_runtime, routes)))
+        })?,
     )
   }
 
@@ -111,12 +206,71 @@ impl ModuleLoader for ExampleModuleLoader {
   }
 }
 
-/// A simple example module that demonstrates a basic HTTP request handler
+/// The routes this example module knows how to handle.
+#[derive(Clone, Copy)]
+enum Route {
+  /// `GET /hello` — responds with a static greeting.
+  Hello,
+  /// `GET /users/:id` — echoes back the captured `id` parameter.
+  UserById,
+  /// `GET /proxy` — sends a fixed `GET` to an upstream URL via
+  /// `OutboundHttp` and relays its response; the incoming request itself is
+  /// not forwarded.
+  Proxy,
+}
+
+/// Builds the route table shared by every `ExampleModule` instance.
 ///
-/// This is implemented as a zero-sized struct since it doesn't need to store any state.
-/// In more complex modules, this would typically contain configuration parameters,
-/// connection pools, or other state needed by the handlers.
-struct ExampleModule;
+/// Pulled out on its own so [`ExampleModuleLoader::on_config_changed`] can
+/// rebuild it without reaching into module construction.
+fn build_routes() -> ModuleRouter<Route> {
+  let mut router = ModuleRouter::new();
+  router.insert("/hello", MethodFilter::GET, Route::Hello);
+  router.insert("/users/:id", MethodFilter::GET, Route::UserById);
+  router.insert("/proxy", MethodFilter::GET, Route::Proxy);
+  router
+}
+
+/// An example module that demonstrates static/dynamic path routing, a
+/// layered middleware stack, and proxying a request upstream.
+///
+/// The layer stack and outbound client are built once, when the module is
+/// constructed, and then shared (via `Arc`) with every
+/// `ExampleModuleHandlers` instance. The route table is shared with the
+/// `ExampleModuleLoader` that built this module instead, via a
+/// `VersionedEntry`, so a config reload can swap it in without rebuilding
+/// this module.
+struct ExampleModule {
+  routes: Arc<VersionedEntry<ModuleRouter<Route>>>,
+  layers: Arc<Vec<Box<dyn ModuleLayer>>>,
+  templates: Arc<TemplateEngine>,
+  outbound: Arc<OutboundHttp>,
+}
+
+impl ExampleModule {
+  fn new(secondary_runtime: &tokio::runtime::Runtime, routes: Arc<VersionedEntry<ModuleRouter<Route>>>) -> Self {
+    // `Logger` is the outermost layer, so it sees every request and
+    // response that passes through this module, including ones the
+    // terminal handler passes through unhandled.
+    let layers: Vec<Box<dyn ModuleLayer>> = vec![Box::new(Logger)];
+
+    let mut templates = TemplateEngine::new();
+    // `id` is attacker-controlled (it's a path segment), so it's rendered
+    // through the default HTML-escaping interpolation rather than `raw()`.
+    templates.register_template("user", "<p>User: {{ id }}</p>");
+
+    // Outbound requests run on the secondary runtime, not the one driving
+    // this request, so a slow upstream can't starve request handling.
+    let outbound = OutboundHttp::new(secondary_runtime, HostPolicy::default());
+
+    Self {
+      routes,
+      layers: Arc::new(layers),
+      templates: Arc::new(templates),
+      outbound,
+    }
+  }
+}
 
 impl Module for ExampleModule {
   /// Creates and returns handler instances for this module
@@ -124,17 +278,31 @@ impl Module for ExampleModule {
   /// # Returns
   /// A boxed trait object implementing the ModuleHandlers trait
   fn get_module_handlers(&self) -> Box<dyn ModuleHandlers> {
-    // Create a new instance of our handlers and box it
-    Box::new(ExampleModuleHandlers)
+    // Hand out clones of the shared route entry, layer stack, and outbound
+    // client, not rebuilt ones
+    Box::new(ExampleModuleHandlers {
+      routes: self.routes.clone(),
+      layers: self.layers.clone(),
+      templates: self.templates.clone(),
+      outbound: self.outbound.clone(),
+    })
   }
 }
 
 /// Handlers that process HTTP requests for the example module
 ///
-/// This implementation demonstrates a simple path-based routing system
-/// that responds with "Hello World!" for requests to "/hello".
-/// For all other paths, it passes the request through without modification.
-struct ExampleModuleHandlers;
+/// This implementation demonstrates routing via [`ModuleRouter`]: static
+/// routes (`/hello`, `/proxy`) and a dynamic one (`/users/:id`) share the
+/// same lookup. A registered path requested with another method gets a 405;
+/// for all other paths, it passes the request through without modification.
+/// The actual matching logic lives in [`TerminalHandler`] so it can sit at
+/// the end of the [`ModuleLayer`] stack built in [`ExampleModule::new`].
+struct ExampleModuleHandlers {
+  routes: Arc<VersionedEntry<ModuleRouter<Route>>>,
+  layers: Arc<Vec<Box<dyn ModuleLayer>>>,
+  templates: Arc<TemplateEngine>,
+  outbound: Arc<OutboundHttp>,
+}
 
 #[async_trait(?Send)]
 impl ModuleHandlers for ExampleModuleHandlers {
@@ -142,46 +310,157 @@ impl ModuleHandlers for ExampleModuleHandlers {
   ///
   /// # Parameters
   /// * `request` - The incoming HTTP request with body
-  /// * `_config` - Server configuration (unused in this example)
-  /// * `_socket_data` - Socket connection information (unused in this example)
-  /// * `_error_logger` - Logger for recording errors (unused in this example)
+  /// * `config` - Server configuration, threaded through to any layers
+  /// * `socket_data` - Socket connection information, threaded through to any layers
+  /// * `error_logger` - Logger for recording errors, used by the `Logger` layer
   ///
   /// # Returns
   /// A ResponseData struct containing either a response or the original request
   async fn request_handler(
+    &mut self,
+    request: Request<BoxBody<Bytes, std::io::Error>>,
+    config: &ServerConfiguration,
+    socket_data: &SocketData,
+    error_logger: &ErrorLogger,
+  ) -> Result<ResponseData, Box<dyn Error + Send + Sync>> {
+    let mut terminal = TerminalHandler {
+      // `get()` resolves to whatever route table is current at the moment
+      // this request arrives; a reload swapping it in mid-flight doesn't
+      // affect requests that already captured the previous `Arc`.
+      router: self.routes.get(),
+      templates: self.templates.clone(),
+      outbound: self.outbound.clone(),
+    };
+
+    LayerStack::new(&self.layers)
+      .run(request, config, socket_data, error_logger, &mut terminal)
+      .await
+  }
+
+  // Note: This module doesn't override response_modifying_handler
+  // so it uses the default implementation from the trait
+}
+
+/// The innermost link in the module's layer chain: does the actual route
+/// matching and builds the response, with no further handler to delegate to.
+struct TerminalHandler {
+  router: Arc<ModuleRouter<Route>>,
+  templates: Arc<TemplateEngine>,
+  outbound: Arc<OutboundHttp>,
+}
+
+#[async_trait(?Send)]
+impl Next for TerminalHandler {
+  async fn run(
     &mut self,
     request: Request<BoxBody<Bytes, std::io::Error>>,
     _config: &ServerConfiguration,
     _socket_data: &SocketData,
     _error_logger: &ErrorLogger,
   ) -> Result<ResponseData, Box<dyn Error + Send + Sync>> {
-    // Check if the request is for the "/hello" path
-    let is_hello = request.uri().path() == "/hello";
+    let path = request.uri().path().to_string();
 
-    // Return a ResponseData with appropriate fields
-    Ok(ResponseData {
-      // Include the original request (required for non-handled routes)
-      request: Some(request),
+    // Look up the route before moving `request` into the response below.
+    let route_match = self.router.lookup(&path, request.method());
 
-      // If path is "/hello", create a response with "Hello World!" body
-      // Otherwise, return None to let other modules handle the request
-      response: if is_hello {
+    let (response, response_status) = match route_match {
+      RouteMatch::Matched {
+        value: Route::Hello,
+        ..
+      } => (
         Some(
           Response::builder().body(
             Full::new("Hello World!".into())
               .map_err(|e| match e {}) // Empty match because Full::new never fails
               .boxed(),
           )?,
-        )
-      } else {
-        None
-      },
-      response_status: None,    // No special status code needed
+        ),
+        None,
+      ),
+      RouteMatch::Matched {
+        value: Route::UserById,
+        params,
+      } => {
+        let id = params
+          .into_iter()
+          .find(|(name, _)| name == "id")
+          .map(|(_, value)| value)
+          .unwrap_or_default();
+
+        // The router skips empty path segments, so `/users/` never reaches
+        // this arm and `id` is not empty in practice; this guard only covers
+        // a route table without an `id` capture.
+        // `.with_status(BAD_REQUEST)` records the status this handler
+        // wants rendered, but nothing downstream downcasts `HandlerError`
+        // back out of the returned `Box<dyn Error>` yet (see error.rs), so
+        // today this still surfaces as the server's generic error response,
+        // not an actual 400 — this is here to show the intended usage, not
+        // a working status override.
+        if id.is_empty() {
+          return Err(
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing user id")
+              .with_status(hyper::StatusCode::BAD_REQUEST)
+              .into(),
+          );
+        }
+
+        let mut context = Context::new();
+        context.insert("id".to_string(), id.into());
+
+        // `id` came straight off the URL, so the template's default
+        // escaping is what keeps a path like `/users/<script>` inert.
+        let body = self
+          .templates
+          .render("user", &context)
+          .expect("the \"user\" template is registered in ExampleModule::new");
+
+        (Some(Response::builder().body(body)?), None)
+      }
+      RouteMatch::Matched {
+        value: Route::Proxy,
+        ..
+      } => {
+        let upstream_request = Request::builder()
+          .method("GET")
+          .uri("http://127.0.0.1:8080/upstream")
+          .body(Empty::new().map_err(|e| match e {}).boxed())?;
+
+        match self.outbound.send(upstream_request).await {
+          Ok(upstream_response) => {
+            let (parts, body) = upstream_response.into_parts();
+            let body = body
+              .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))
+              .boxed();
+            (Some(Response::from_parts(parts, body)), None)
+          }
+          Err(_) => (
+            Some(
+              Response::builder().body(
+                Full::new(Bytes::from_static(b"bad gateway"))
+                  .map_err(|e| match e {})
+                  .boxed(),
+              )?,
+            ),
+            Some(hyper::StatusCode::BAD_GATEWAY),
+          ),
+        }
+      }
+      RouteMatch::MethodNotAllowed(_) => (
+        Some(Response::builder().body(Full::new(Bytes::new()).map_err(|e| match e {}).boxed())?),
+        Some(hyper::StatusCode::METHOD_NOT_ALLOWED),
+      ),
+      // Path isn't one we handle; let other modules have a go.
+      RouteMatch::NotFound => (None, None),
+    };
+
+    // Return a ResponseData with appropriate fields
+    Ok(ResponseData {
+      // Include the original request (required for non-handled routes)
+      request: Some(request),
+      response,
+      response_status,
       response_headers: None,   // No additional headers needed
       new_remote_address: None, // No address rewriting needed
     })
   }
-
-  // Note: This module doesn't override response_modifying_handler
-  // so it uses the default implementation from the trait
 }
@@ -0,0 +1,137 @@
+//! A Tower/Actix-style layering API for composing cross-cutting behavior
+//! (logging, auth, rate limiting, ...) around a module's terminal handler,
+//! without each module reimplementing the composition itself.
+//!
+//! A [`ModuleLayer`] wraps the call to the handler it's layered around; it
+//! decides whether, when, and with what request to invoke [`Next`], and can
+//! inspect or rewrite the resulting `ResponseData`. A module composes its own
+//! layers: its `request_handler` runs a [`LayerStack`] around its terminal
+//! handler, in the order the layers are listed — the first one is the
+//! outermost. `Module::get_module_handlers` returns only the handlers, and the
+//! server itself does not compose layers; it calls `request_handler` directly.
+
+use std::error::Error;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use http_body_util::combinators::BoxBody;
+use hyper::Request;
+
+use ferron_common::config::ServerConfiguration;
+use ferron_common::logging::ErrorLogger;
+use ferron_common::modules::{ResponseData, SocketData};
+
+/// The remainder of the handler chain a [`ModuleLayer`] is wrapping.
+///
+/// Calling [`Next::run`] invokes whatever is next in the chain — either
+/// another layer or the module's terminal handler — exactly
+/// like calling the inner service in a Tower middleware.
+#[async_trait(?Send)]
+pub trait Next {
+  async fn run(
+    &mut self,
+    request: Request<BoxBody<Bytes, std::io::Error>>,
+    config: &ServerConfiguration,
+    socket_data: &SocketData,
+    error_logger: &ErrorLogger,
+  ) -> Result<ResponseData, Box<dyn Error + Send + Sync>>;
+}
+
+/// A single layer in a module's middleware stack.
+///
+/// Implementors decide what happens before and after the wrapped handler
+/// runs: short-circuit with their own response, mutate the request on the
+/// way in, or inspect/rewrite the response on the way out.
+#[async_trait(?Send)]
+pub trait ModuleLayer {
+  async fn wrap(
+    &self,
+    request: Request<BoxBody<Bytes, std::io::Error>>,
+    config: &ServerConfiguration,
+    socket_data: &SocketData,
+    error_logger: &ErrorLogger,
+    next: &mut dyn Next,
+  ) -> Result<ResponseData, Box<dyn Error + Send + Sync>>;
+}
+
+/// Composes a stack of `ModuleLayer`s around a terminal [`Next`], so a
+/// module's `request_handler` can drive the whole chain with a single call.
+///
+/// `layers` is in outermost-to-innermost order.
+pub struct LayerStack<'a> {
+  layers: &'a [Box<dyn ModuleLayer>],
+}
+
+impl<'a> LayerStack<'a> {
+  pub fn new(layers: &'a [Box<dyn ModuleLayer>]) -> Self {
+    Self { layers }
+  }
+
+  /// Runs the stack around `terminal`, which plays the role of the
+  /// innermost `Next` (the module's own terminal handler).
+  pub async fn run(
+    &self,
+    request: Request<BoxBody<Bytes, std::io::Error>>,
+    config: &ServerConfiguration,
+    socket_data: &SocketData,
+    error_logger: &ErrorLogger,
+    terminal: &mut dyn Next,
+  ) -> Result<ResponseData, Box<dyn Error + Send + Sync>> {
+    match self.layers.split_first() {
+      None => terminal.run(request, config, socket_data, error_logger).await,
+      Some((layer, rest)) => {
+        let mut remainder = RemainingLayers {
+          stack: LayerStack { layers: rest },
+          terminal,
+        };
+        layer.wrap(request, config, socket_data, error_logger, &mut remainder).await
+      }
+    }
+  }
+}
+
+/// Glue that lets [`LayerStack::run`] recurse through the remaining layers
+/// via the `Next` trait, ending at the original `terminal`.
+struct RemainingLayers<'s, 't> {
+  stack: LayerStack<'s>,
+  terminal: &'t mut dyn Next,
+}
+
+#[async_trait(?Send)]
+impl<'s, 't> Next for RemainingLayers<'s, 't> {
+  async fn run(
+    &mut self,
+    request: Request<BoxBody<Bytes, std::io::Error>>,
+    config: &ServerConfiguration,
+    socket_data: &SocketData,
+    error_logger: &ErrorLogger,
+  ) -> Result<ResponseData, Box<dyn Error + Send + Sync>> {
+    self.stack.run(request, config, socket_data, error_logger, self.terminal).await
+  }
+}
+
+/// A layer that logs the method and path of every request it sees, before
+/// and after the wrapped handler runs — the module-level analogue of the
+/// `Logger` middleware from the actix example.
+pub struct Logger;
+
+#[async_trait(?Send)]
+impl ModuleLayer for Logger {
+  async fn wrap(
+    &self,
+    request: Request<BoxBody<Bytes, std::io::Error>>,
+    config: &ServerConfiguration,
+    socket_data: &SocketData,
+    error_logger: &ErrorLogger,
+    next: &mut dyn Next,
+  ) -> Result<ResponseData, Box<dyn Error + Send + Sync>> {
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+
+    error_logger.log(&format!("--> {method} {path}")).await;
+    let result = next.run(request, config, socket_data, error_logger).await;
+    error_logger.log(&format!("<-- {method} {path}")).await;
+
+    result
+  }
+}
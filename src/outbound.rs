@@ -0,0 +1,243 @@
+//! An outbound HTTP client host object, so a handler can call an upstream
+//! service (proxying, auth token introspection, fetching config) without
+//! every module that needs one building its own hyper client and pool.
+//!
+//! Modelled on the outbound host-component pattern used by WASM runtimes:
+//! modules are handed a single typed client (`OutboundHttp`) and call
+//! `outbound.send(request).await` rather than owning their own connector.
+//! Connections are pooled per authority, and a [`HostPolicy`] bounds how
+//! many requests may be in flight to a given host at once and how long a
+//! request is allowed to take before it's cancelled.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use bytes::Bytes;
+use http_body_util::combinators::BoxBody;
+use hyper::body::Incoming;
+use hyper::client::conn::http1;
+use hyper::{Request, Response};
+use tokio::net::TcpStream;
+use tokio::sync::{Mutex, OnceCell, Semaphore};
+
+/// Per-host limits applied by [`OutboundHttp`] before a request is sent.
+#[derive(Debug, Clone, Copy)]
+pub struct HostPolicy {
+  /// Maximum number of requests that may be in flight to a single
+  /// authority at once.
+  pub max_concurrency: usize,
+  /// How long to wait for the response before giving up.
+  pub timeout: Duration,
+}
+
+impl Default for HostPolicy {
+  fn default() -> Self {
+    Self {
+      max_concurrency: 32,
+      timeout: Duration::from_secs(10),
+    }
+  }
+}
+
+/// A pooled connection to one authority, guarded by a mutex so concurrent
+/// callers don't interleave requests on the same hyper connection.
+struct PooledConnection {
+  sender: Mutex<http1::SendRequest<BoxBody<Bytes, std::io::Error>>>,
+  permits: Arc<Semaphore>,
+  /// Flipped to `true` by the background task driving this connection once
+  /// it finishes — e.g. because the upstream closed the keep-alive socket.
+  /// `connection_for` checks this before handing the connection out again,
+  /// so a dead connection gets evicted and redialed instead of being reused
+  /// (and failing) forever.
+  closed: Arc<AtomicBool>,
+}
+
+/// The outbound HTTP host object handed to modules.
+///
+/// Runs every request on the secondary Tokio runtime a [`ModuleLoader`]
+/// receives in `load_module`, and keeps one pooled connection per authority
+/// (`host:port`) instead of dialing a fresh one per request.
+///
+/// [`ModuleLoader`]: ferron_common::modules::ModuleLoader
+pub struct OutboundHttp {
+  runtime: tokio::runtime::Handle,
+  policy: HostPolicy,
+  // Each authority gets its own `OnceCell`, reserved (empty) under the outer
+  // mutex and then dialed outside of it. That keeps two concurrent first
+  // callers to the same authority from both dialing and racing an `insert`
+  // — they share the one `OnceCell` and the second simply awaits the first's
+  // dial — while callers to different authorities never block each other.
+  pool: Mutex<HashMap<String, Arc<OnceCell<Arc<PooledConnection>>>>>,
+}
+
+impl OutboundHttp {
+  /// Creates a client that dispatches requests on `runtime` under `policy`.
+  pub fn new(runtime: &tokio::runtime::Runtime, policy: HostPolicy) -> Arc<Self> {
+    Arc::new(Self {
+      runtime: runtime.handle().clone(),
+      policy,
+      pool: Mutex::new(HashMap::new()),
+    })
+  }
+
+  /// Sends `request` to its `Uri`'s authority, reusing a pooled connection
+  /// when one is already open, subject to this client's [`HostPolicy`].
+  pub async fn send(
+    &self,
+    request: Request<BoxBody<Bytes, std::io::Error>>,
+  ) -> Result<Response<Incoming>, Box<dyn Error + Send + Sync>> {
+    let authority = request
+      .uri()
+      .authority()
+      .ok_or("outbound request URI must have an authority (scheme://host[:port])")?
+      .to_string();
+
+    let connection = self.connection_for(&authority).await?;
+    let _permit = connection.permits.clone().acquire_owned().await?;
+
+    let send = async {
+      let mut sender = connection.sender.lock().await;
+      sender.send_request(request).await.map_err(Into::into)
+    };
+
+    match tokio::time::timeout(self.policy.timeout, send).await {
+      Ok(result) => result,
+      Err(_) => Err(format!("outbound request to {authority} timed out after {:?}", self.policy.timeout).into()),
+    }
+  }
+
+  /// Returns the pooled connection for `authority`, dialing one if none
+  /// exists yet or the existing one has gone stale.
+  async fn connection_for(&self, authority: &str) -> Result<Arc<PooledConnection>, Box<dyn Error + Send + Sync>> {
+    loop {
+      let slot = self
+        .pool
+        .lock()
+        .await
+        .entry(authority.to_string())
+        .or_insert_with(|| Arc::new(OnceCell::new()))
+        .clone();
+
+      let pooled = slot.get_or_try_init(|| self.dial(authority)).await?.clone();
+
+      if !pooled.closed.load(Ordering::Acquire) {
+        return Ok(pooled);
+      }
+
+      // The connection died since it was pooled. Evict it — but only if
+      // nobody else has already replaced this slot — and redial.
+      let mut pool = self.pool.lock().await;
+      if let Some(current) = pool.get(authority) {
+        if Arc::ptr_eq(current, &slot) {
+          pool.remove(authority);
+        }
+      }
+    }
+  }
+
+  /// Dials a fresh connection to `authority` and starts driving it in the
+  /// background on the secondary runtime.
+  async fn dial(&self, authority: &str) -> Result<Arc<PooledConnection>, Box<dyn Error + Send + Sync>> {
+    let stream = TcpStream::connect(authority).await?;
+    let (sender, connection) = http1::handshake(stream).await?;
+
+    let closed = Arc::new(AtomicBool::new(false));
+    let closed_marker = closed.clone();
+    self.runtime.spawn(async move {
+      let _ = connection.await;
+      closed_marker.store(true, Ordering::Release);
+    });
+
+    Ok(Arc::new(PooledConnection {
+      sender: Mutex::new(sender),
+      permits: Arc::new(Semaphore::new(self.policy.max_concurrency)),
+      closed,
+    }))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use http_body_util::BodyExt;
+  use tokio::io::{AsyncReadExt, AsyncWriteExt};
+  use tokio::net::TcpListener;
+
+  use super::*;
+
+  /// Reads one request off `stream`, answers it with a fixed 200 response
+  /// advertising `Connection: close`, then closes the socket — standing in
+  /// for an upstream that doesn't keep the connection alive.
+  async fn respond_once_then_hang_up(mut stream: TcpStream) {
+    let mut received = Vec::new();
+    let mut buf = [0u8; 1024];
+    loop {
+      let n = stream.read(&mut buf).await.unwrap();
+      received.extend_from_slice(&buf[..n]);
+      if n == 0 || received.windows(4).any(|w| w == b"\r\n\r\n") {
+        break;
+      }
+    }
+
+    let body = b"ok";
+    let response = format!(
+      "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+      body.len()
+    );
+    stream.write_all(response.as_bytes()).await.unwrap();
+    stream.write_all(body).await.unwrap();
+    // Fully close the socket (not just the write half) so the client's
+    // connection-driving task sees EOF and finishes promptly, instead of
+    // waiting on a read half we never close.
+    drop(stream);
+  }
+
+  #[test]
+  fn dead_connection_is_evicted_and_redialed() {
+    // `OutboundHttp` dials and drives connections on the runtime it's given,
+    // same as the secondary runtime a real `ModuleLoader` would hand it, so
+    // the test drives everything (server loop, client, assertions) on one
+    // runtime it owns directly instead of needing the ambient `#[tokio::test]` one.
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+      .enable_all()
+      .build()
+      .unwrap();
+
+    runtime.block_on(async {
+      let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+      let addr = listener.local_addr().unwrap();
+
+      tokio::spawn(async move {
+        loop {
+          let Ok((stream, _)) = listener.accept().await else {
+            break;
+          };
+          tokio::spawn(respond_once_then_hang_up(stream));
+        }
+      });
+
+      let client = OutboundHttp::new(&runtime, HostPolicy::default());
+      let request = || {
+        Request::builder()
+          .method("GET")
+          .uri(format!("http://{addr}/"))
+          .body(http_body_util::Empty::new().map_err(|e| match e {}).boxed())
+          .unwrap()
+      };
+
+      let first = client.send(request()).await.unwrap();
+      assert_eq!(first.into_body().collect().await.unwrap().to_bytes(), Bytes::from_static(b"ok"));
+
+      // Give the connection-driving background task a moment to notice the
+      // server closed the socket and flip `closed` before the next `send`.
+      tokio::time::sleep(Duration::from_millis(50)).await;
+
+      // The first connection is now dead. This must redial instead of
+      // reusing (and failing against) the closed one.
+      let second = client.send(request()).await.unwrap();
+      assert_eq!(second.into_body().collect().await.unwrap().to_bytes(), Bytes::from_static(b"ok"));
+    });
+  }
+}
@@ -0,0 +1,47 @@
+//! A small versioned-swap primitive for hot-reloading module state.
+//!
+//! `VersionedEntry<T>` hands out `Arc<T>` clones of whatever value is
+//! current. Swapping in a new value only changes what *future* callers of
+//! [`VersionedEntry::get`] observe — anyone already holding an `Arc` from
+//! before the swap keeps it, and the old value is only actually dropped once
+//! the last of those in-flight `Arc`s goes away. That's what lets a loader
+//! react to a configuration change without interrupting requests that are
+//! already running against the previous instance.
+
+use std::sync::{Arc, RwLock};
+
+pub struct VersionedEntry<T> {
+  current: RwLock<Arc<T>>,
+}
+
+impl<T> VersionedEntry<T> {
+  pub fn new(value: T) -> Self {
+    Self {
+      current: RwLock::new(Arc::new(value)),
+    }
+  }
+
+  /// Returns the currently active value.
+  pub fn get(&self) -> Arc<T> {
+    self.current.read().expect("VersionedEntry lock poisoned").clone()
+  }
+
+  /// Unconditionally swaps in `value`, returning the instance it replaced.
+  pub fn invalidate(&self, value: T) -> Arc<T> {
+    let mut slot = self.current.write().expect("VersionedEntry lock poisoned");
+    std::mem::replace(&mut *slot, Arc::new(value))
+  }
+
+  /// Swaps in the result of `rebuild` only if `predicate` says the current
+  /// value is stale, so callers can skip rebuilding when nothing relevant
+  /// changed. Returns whether a swap happened.
+  pub fn invalidate_if(&self, predicate: impl FnOnce(&T) -> bool, rebuild: impl FnOnce() -> T) -> bool {
+    let mut slot = self.current.write().expect("VersionedEntry lock poisoned");
+    if predicate(&slot) {
+      *slot = Arc::new(rebuild());
+      true
+    } else {
+      false
+    }
+  }
+}
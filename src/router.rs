@@ -0,0 +1,349 @@
+//! A small path router that modules can build once at load time and query
+//! from inside `request_handler`, instead of hand-rolling string comparisons
+//! against `request.uri().path()`.
+//!
+//! Routes are matched against a segment-level trie: each node covers one
+//! whole path segment (keyed by the full segment string, not a
+//! character-level radix/PATRICIA split), and may have any number of static
+//! children plus at most one `:name` (single dynamic segment) child and one
+//! `*name` (catch-all) child. Lookup always prefers a static match over a
+//! param match, and a param match over a catch-all, mirroring the precedence
+//! rules used by most HTTP routers.
+
+use std::collections::HashMap;
+
+use hyper::Method;
+
+/// A bitset of HTTP methods a route accepts.
+///
+/// Lets a single path (e.g. `/users/:id`) be registered with different
+/// handlers per method, and lets lookup distinguish "path matched, but not
+/// this method" (405) from "path didn't match at all" (fall through / 404).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MethodFilter(u16);
+
+impl MethodFilter {
+  pub const GET: Self = Self(1 << 0);
+  pub const POST: Self = Self(1 << 1);
+  pub const PUT: Self = Self(1 << 2);
+  pub const DELETE: Self = Self(1 << 3);
+  pub const PATCH: Self = Self(1 << 4);
+  pub const HEAD: Self = Self(1 << 5);
+  pub const OPTIONS: Self = Self(1 << 6);
+  pub const CONNECT: Self = Self(1 << 7);
+  pub const TRACE: Self = Self(1 << 8);
+
+  /// A filter that accepts every method.
+  pub const ANY: Self = Self(u16::MAX);
+
+  /// Returns the filter bit for a `hyper::Method`, if it's one Ferron knows
+  /// how to represent. Unrecognized/custom methods never match a non-`ANY`
+  /// filter.
+  fn bit_for(method: &Method) -> Option<Self> {
+    Some(match *method {
+      Method::GET => Self::GET,
+      Method::POST => Self::POST,
+      Method::PUT => Self::PUT,
+      Method::DELETE => Self::DELETE,
+      Method::PATCH => Self::PATCH,
+      Method::HEAD => Self::HEAD,
+      Method::OPTIONS => Self::OPTIONS,
+      Method::CONNECT => Self::CONNECT,
+      Method::TRACE => Self::TRACE,
+      _ => return None,
+    })
+  }
+
+  /// Whether `method` is accepted by this filter.
+  pub fn matches(&self, method: &Method) -> bool {
+    if *self == Self::ANY {
+      return true;
+    }
+    match Self::bit_for(method) {
+      Some(bit) => self.0 & bit.0 != 0,
+      None => false,
+    }
+  }
+}
+
+impl std::ops::BitOr for MethodFilter {
+  type Output = Self;
+
+  fn bitor(self, rhs: Self) -> Self {
+    Self(self.0 | rhs.0)
+  }
+}
+
+/// The outcome of a [`ModuleRouter::lookup`] call.
+pub enum RouteMatch<'a, T> {
+  /// A route matched both the path and the method.
+  Matched {
+    value: &'a T,
+    /// Captured `:name`/`*name` values, in the order they appear in the path.
+    params: Vec<(String, String)>,
+  },
+  /// The path matched a registered route, but not for this method. Carries
+  /// the union of methods that *would* have matched, for building an
+  /// `Allow` header on the resulting 405 response.
+  MethodNotAllowed(MethodFilter),
+  /// No registered route matches the path at all.
+  NotFound,
+}
+
+#[derive(Default)]
+struct Node<T> {
+  static_children: HashMap<String, Node<T>>,
+  param_child: Option<(String, Box<Node<T>>)>,
+  catch_all_child: Option<(String, Box<Node<T>>)>,
+  routes: Vec<(MethodFilter, T)>,
+}
+
+impl<T> Node<T> {
+  fn new() -> Self {
+    Self {
+      static_children: HashMap::new(),
+      param_child: None,
+      catch_all_child: None,
+      routes: Vec::new(),
+    }
+  }
+}
+
+/// A trie-based router mapping `(method, path)` pairs to values of type `T`.
+///
+/// Build it once when the module loads, then call [`ModuleRouter::lookup`]
+/// for every request.
+pub struct ModuleRouter<T> {
+  root: Node<T>,
+}
+
+impl<T> Default for ModuleRouter<T> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<T> ModuleRouter<T> {
+  /// Creates an empty router.
+  pub fn new() -> Self {
+    Self { root: Node::new() }
+  }
+
+  /// Registers `value` for `path` under the given `methods`.
+  ///
+  /// `path` segments starting with `:` are captured as named parameters
+  /// (e.g. `:id`), and a segment starting with `*` is a catch-all that
+  /// consumes the rest of the path (e.g. `*rest`) and must be the last
+  /// segment.
+  ///
+  /// # Panics
+  ///
+  /// Panics if a `:name` (or `*name`) segment is registered at a position
+  /// that already has a param (or catch-all) child under a *different*
+  /// name — e.g. inserting both `/users/:id` and `/users/:slug`. Only one
+  /// param name is tracked per position, so silently allowing a second name
+  /// there would mean whichever route registered first decides what every
+  /// route matching that position calls the captured value.
+  pub fn insert(&mut self, path: &str, methods: MethodFilter, value: T) {
+    let mut node = &mut self.root;
+
+    for segment in path.split('/').filter(|s| !s.is_empty()) {
+      if let Some(name) = segment.strip_prefix(':') {
+        let entry = node
+          .param_child
+          .get_or_insert_with(|| (name.to_string(), Box::new(Node::new())));
+        assert_eq!(
+          entry.0, name,
+          "conflicting param names at the same position in the route tree: `:{}` vs `:{}`",
+          entry.0, name
+        );
+        node = &mut entry.1;
+      } else if let Some(name) = segment.strip_prefix('*') {
+        let entry = node
+          .catch_all_child
+          .get_or_insert_with(|| (name.to_string(), Box::new(Node::new())));
+        assert_eq!(
+          entry.0, name,
+          "conflicting catch-all names at the same position in the route tree: `*{}` vs `*{}`",
+          entry.0, name
+        );
+        node = &mut entry.1;
+        break;
+      } else {
+        node = node
+          .static_children
+          .entry(segment.to_string())
+          .or_insert_with(Node::new);
+      }
+    }
+
+    node.routes.push((methods, value));
+  }
+
+  /// Looks up the route matching `path` and `method`.
+  pub fn lookup(&self, path: &str, method: &Method) -> RouteMatch<'_, T> {
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    let mut params = Vec::new();
+
+    match Self::walk(&self.root, &segments, method, &mut params) {
+      Some(value) => RouteMatch::Matched { value, params },
+      None => {
+        params.clear();
+        match Self::allowed_methods(&self.root, &segments) {
+          Some(allowed) => RouteMatch::MethodNotAllowed(allowed),
+          None => RouteMatch::NotFound,
+        }
+      }
+    }
+  }
+
+  fn walk<'a>(
+    node: &'a Node<T>,
+    segments: &[&str],
+    method: &Method,
+    params: &mut Vec<(String, String)>,
+  ) -> Option<&'a T> {
+    if segments.is_empty() {
+      return node
+        .routes
+        .iter()
+        .find(|(filter, _)| filter.matches(method))
+        .map(|(_, value)| value);
+    }
+
+    let (segment, rest) = (segments[0], &segments[1..]);
+
+    // Static > param > catch-all, as documented.
+    if let Some(child) = node.static_children.get(segment) {
+      if let Some(value) = Self::walk(child, rest, method, params) {
+        return Some(value);
+      }
+    }
+
+    if let Some((name, child)) = &node.param_child {
+      let checkpoint = params.len();
+      params.push((name.clone(), segment.to_string()));
+      if let Some(value) = Self::walk(child, rest, method, params) {
+        return Some(value);
+      }
+      params.truncate(checkpoint);
+    }
+
+    if let Some((name, child)) = &node.catch_all_child {
+      let remainder = segments.join("/");
+      if let Some((_, value)) = child.routes.iter().find(|(filter, _)| filter.matches(method)) {
+        params.push((name.clone(), remainder));
+        return Some(value);
+      }
+    }
+
+    None
+  }
+
+  /// Finds the union of methods accepted by every route whose path matches
+  /// `segments`, ignoring the requested method. Used to distinguish 404 from
+  /// 405.
+  fn allowed_methods(node: &Node<T>, segments: &[&str]) -> Option<MethodFilter> {
+    if segments.is_empty() {
+      return node
+        .routes
+        .iter()
+        .map(|(filter, _)| *filter)
+        .reduce(|a, b| a | b);
+    }
+
+    let (segment, rest) = (segments[0], &segments[1..]);
+    let mut allowed: Option<MethodFilter> = None;
+
+    if let Some(child) = node.static_children.get(segment) {
+      if let Some(filter) = Self::allowed_methods(child, rest) {
+        allowed = Some(allowed.map_or(filter, |a| a | filter));
+      }
+    }
+
+    if let Some((_, child)) = &node.param_child {
+      if let Some(filter) = Self::allowed_methods(child, rest) {
+        allowed = Some(allowed.map_or(filter, |a| a | filter));
+      }
+    }
+
+    if let Some((_, child)) = &node.catch_all_child {
+      if let Some(filter) = child.routes.iter().map(|(filter, _)| *filter).reduce(|a, b| a | b) {
+        allowed = Some(allowed.map_or(filter, |a| a | filter));
+      }
+    }
+
+    allowed
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn router() -> ModuleRouter<&'static str> {
+    let mut router = ModuleRouter::new();
+    router.insert("/hello", MethodFilter::GET, "hello");
+    router.insert("/users/:id", MethodFilter::GET | MethodFilter::POST, "user");
+    router.insert("/files/*rest", MethodFilter::GET, "files");
+    router
+  }
+
+  #[test]
+  fn static_route_matches() {
+    match router().lookup("/hello", &Method::GET) {
+      RouteMatch::Matched { value, params } => {
+        assert_eq!(*value, "hello");
+        assert!(params.is_empty());
+      }
+      _ => panic!("expected a match"),
+    }
+  }
+
+  #[test]
+  fn static_beats_param_when_both_could_match() {
+    let mut router = ModuleRouter::new();
+    router.insert("/users/me", MethodFilter::GET, "me");
+    router.insert("/users/:id", MethodFilter::GET, "by_id");
+
+    match router.lookup("/users/me", &Method::GET) {
+      RouteMatch::Matched { value, .. } => assert_eq!(*value, "me"),
+      _ => panic!("expected the static route to win"),
+    }
+  }
+
+  #[test]
+  fn param_route_captures_the_segment() {
+    match router().lookup("/users/42", &Method::GET) {
+      RouteMatch::Matched { value, params } => {
+        assert_eq!(*value, "user");
+        assert_eq!(params, vec![("id".to_string(), "42".to_string())]);
+      }
+      _ => panic!("expected a match"),
+    }
+  }
+
+  #[test]
+  fn catch_all_captures_the_remainder() {
+    match router().lookup("/files/a/b/c", &Method::GET) {
+      RouteMatch::Matched { value, params } => {
+        assert_eq!(*value, "files");
+        assert_eq!(params, vec![("rest".to_string(), "a/b/c".to_string())]);
+      }
+      _ => panic!("expected a match"),
+    }
+  }
+
+  #[test]
+  fn wrong_method_is_method_not_allowed_not_not_found() {
+    match router().lookup("/hello", &Method::POST) {
+      RouteMatch::MethodNotAllowed(allowed) => assert!(allowed.matches(&Method::GET)),
+      _ => panic!("expected 405, not a match or 404"),
+    }
+  }
+
+  #[test]
+  fn unregistered_path_is_not_found() {
+    assert!(matches!(router().lookup("/nope", &Method::GET), RouteMatch::NotFound));
+  }
+}
@@ -0,0 +1,266 @@
+//! A small template facility for building response bodies without resorting
+//! to hand-rolled string concatenation.
+//!
+//! Templates are registered once under a name, then rendered against a
+//! context of [`Value`]s. Interpolated values are HTML-escaped by default —
+//! `<` and `>` become the JS-unicode escapes `\u003c`/`\u003e` rather than
+//! `&lt;`/`&gt;`, and `&` and quote characters are replaced with the HTML
+//! entities `&amp;`, `&quot;` and `&#39;`, so a value stays inert even when it's dropped into an
+//! inline `<script>` block rather than just regular HTML or an attribute.
+//! Wrap a value in [`raw`] to opt out when it's already trusted, pre-escaped
+//! markup.
+
+use std::collections::HashMap;
+use std::io;
+
+use bytes::Bytes;
+use http_body_util::combinators::BoxBody;
+use http_body_util::{BodyExt, Full};
+
+/// A value that can be interpolated into a template, or used as the subject
+/// of a `{{:name:}}` loop/conditional block.
+#[derive(Debug, Clone)]
+pub enum Value {
+  String(String),
+  Bool(bool),
+  List(Vec<HashMap<String, Value>>),
+  /// Already-escaped (or otherwise trusted) markup — rendered verbatim.
+  Raw(String),
+}
+
+impl Value {
+  fn render(&self) -> String {
+    match self {
+      Value::String(s) => escape_html(s),
+      Value::Bool(b) => b.to_string(),
+      Value::List(_) => String::new(),
+      Value::Raw(s) => s.clone(),
+    }
+  }
+
+  fn truthy(&self) -> bool {
+    match self {
+      Value::String(s) => !s.is_empty(),
+      Value::Bool(b) => *b,
+      Value::List(items) => !items.is_empty(),
+      Value::Raw(s) => !s.is_empty(),
+    }
+  }
+}
+
+impl From<&str> for Value {
+  fn from(s: &str) -> Self {
+    Value::String(s.to_string())
+  }
+}
+
+impl From<String> for Value {
+  fn from(s: String) -> Self {
+    Value::String(s)
+  }
+}
+
+impl From<bool> for Value {
+  fn from(b: bool) -> Self {
+    Value::Bool(b)
+  }
+}
+
+/// Wraps `markup` so it's inserted into the rendered output verbatim,
+/// bypassing HTML escaping. Only use this for markup you already trust.
+pub fn raw(markup: impl Into<String>) -> Value {
+  Value::Raw(markup.into())
+}
+
+/// HTML-escapes `input`: `&` and both quote characters are replaced with
+/// the HTML entities `&amp;`, `&quot;` and `&#39;`, while `<` and `>` become the JS-unicode
+/// escapes `\u003c`/`\u003e` rather than `&lt;`/`&gt;`, so an escaped value
+/// stays inert even when it ends up inside an inline `<script>` block, not
+/// just in regular HTML or a quoted attribute.
+fn escape_html(input: &str) -> String {
+  let mut out = String::with_capacity(input.len());
+  for ch in input.chars() {
+    match ch {
+      '<' => out.push_str("\\u003c"),
+      '>' => out.push_str("\\u003e"),
+      '&' => out.push_str("&amp;"),
+      '"' => out.push_str("&quot;"),
+      '\'' => out.push_str("&#39;"),
+      _ => out.push(ch),
+    }
+  }
+  out
+}
+
+/// A rendering context: the set of named values a template's `{{ name }}`
+/// interpolations and `{{:name:}}` blocks are resolved against.
+pub type Context = HashMap<String, Value>;
+
+/// A registry of named templates, registered once and rendered as many times
+/// as needed. Sources are stored as text and parsed on every render.
+#[derive(Default)]
+pub struct TemplateEngine {
+  templates: HashMap<String, String>,
+}
+
+impl TemplateEngine {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Registers `source` under `name`, overwriting any existing template
+  /// with that name.
+  pub fn register_template(&mut self, name: impl Into<String>, source: impl Into<String>) {
+    self.templates.insert(name.into(), source.into());
+  }
+
+  /// Renders the template registered as `name` against `context`, returning
+  /// a body ready to drop into a `ResponseData`.
+  ///
+  /// Returns `None` if no template was registered under `name`.
+  pub fn render(&self, name: &str, context: &Context) -> Option<BoxBody<Bytes, io::Error>> {
+    let source = self.templates.get(name)?;
+    let rendered = render_source(source, context);
+
+    Some(Full::new(Bytes::from(rendered)).map_err(|e| match e {}).boxed())
+  }
+}
+
+/// Renders `source` against `context`, supporting `{{ name }}` interpolation
+/// and `{{:name:}}...{{/name}}` blocks: truthy scalars render their body
+/// once, lists render it once per item (with the item's fields merged into
+/// the block's context), and falsy/empty values render nothing.
+fn render_source(source: &str, context: &Context) -> String {
+  let mut out = String::with_capacity(source.len());
+  let mut rest = source;
+
+  while let Some(start) = rest.find("{{") {
+    out.push_str(&rest[..start]);
+    let after_open = &rest[start + 2..];
+
+    if let Some(name) = after_open.strip_prefix(':').and_then(|s| s.split_once(':').map(|(n, _)| n)) {
+      // A well-formed open tag always contains the `:}}` that closes it;
+      // if it's missing (e.g. `{{:foo}}` with no second colon), this isn't
+      // actually a block tag, so fall back to the same "stop here" handling
+      // as an unclosed block rather than panicking.
+      let Some(block_colon_idx) = after_open.find(":}}") else {
+        out.push_str(after_open);
+        return out;
+      };
+      let block_start = block_colon_idx + 3;
+      let close_tag = format!("{{{{/{name}}}}}");
+      let Some(close_idx) = after_open.find(&close_tag) else {
+        // Unclosed block: treat the rest as literal text and stop.
+        out.push_str(after_open);
+        return out;
+      };
+
+      let body = &after_open[block_start..close_idx];
+      out.push_str(&render_block(name, body, context));
+
+      rest = &after_open[close_idx + close_tag.len()..];
+      continue;
+    }
+
+    let Some(end) = after_open.find("}}") else {
+      out.push_str("{{");
+      rest = after_open;
+      continue;
+    };
+
+    let name = after_open[..end].trim();
+    if let Some(value) = context.get(name) {
+      out.push_str(&value.render());
+    }
+    rest = &after_open[end + 2..];
+  }
+
+  out.push_str(rest);
+  out
+}
+
+fn render_block(name: &str, body: &str, context: &Context) -> String {
+  match context.get(name) {
+    Some(Value::List(items)) => items
+      .iter()
+      .map(|item| {
+        let mut merged = context.clone();
+        merged.extend(item.iter().map(|(k, v)| (k.clone(), v.clone())));
+        render_source(body, &merged)
+      })
+      .collect(),
+    Some(value) if value.truthy() => render_source(body, context),
+    _ => String::new(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn escapes_angle_brackets_as_unicode_escapes_not_entities() {
+    assert_eq!(escape_html("<script>"), "\\u003cscript\\u003e");
+    assert_eq!(escape_html("a & b \"c\" 'd'"), "a &amp; b &quot;c&quot; &#39;d&#39;");
+  }
+
+  #[test]
+  fn raw_values_bypass_escaping() {
+    let mut context = Context::new();
+    context.insert("markup".to_string(), raw("<b>bold</b>"));
+    assert_eq!(render_source("{{ markup }}", &context), "<b>bold</b>");
+  }
+
+  #[test]
+  fn interpolated_values_are_escaped() {
+    let mut context = Context::new();
+    context.insert("id".to_string(), "<script>".into());
+    assert_eq!(render_source("{{ id }}", &context), "\\u003cscript\\u003e");
+  }
+
+  #[test]
+  fn truthy_block_renders_its_body_once() {
+    let mut context = Context::new();
+    context.insert("flag".to_string(), true.into());
+    assert_eq!(render_source("{{:flag:}}shown{{/flag}}", &context), "shown");
+  }
+
+  #[test]
+  fn falsy_block_renders_nothing() {
+    let mut context = Context::new();
+    context.insert("flag".to_string(), false.into());
+    assert_eq!(render_source("{{:flag:}}shown{{/flag}}", &context), "");
+  }
+
+  #[test]
+  fn list_block_renders_once_per_item() {
+    let mut item_a = HashMap::new();
+    item_a.insert("name".to_string(), "a".into());
+    let mut item_b = HashMap::new();
+    item_b.insert("name".to_string(), "b".into());
+
+    let mut context = Context::new();
+    context.insert("items".to_string(), Value::List(vec![item_a, item_b]));
+
+    assert_eq!(render_source("{{:items:}}{{ name }},{{/items}}", &context), "a,b,");
+  }
+
+  #[test]
+  fn malformed_block_open_tag_does_not_panic() {
+    // Missing the second `:` before `}}` — not a valid block tag, so the
+    // rest of the source is emitted as literal text instead of panicking.
+    assert_eq!(render_source("{{:foo}}bar: baz", &Context::new()), ":foo}}bar: baz");
+  }
+
+  #[test]
+  fn unclosed_block_stops_instead_of_panicking() {
+    let mut context = Context::new();
+    context.insert("flag".to_string(), true.into());
+    // No matching `{{/flag}}`: everything after the open tag's `{{` is
+    // emitted as literal text instead of panicking.
+    assert_eq!(
+      render_source("before{{:flag:}}never closed", &context),
+      "before:flag:}}never closed"
+    );
+  }
+}
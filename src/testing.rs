@@ -0,0 +1,312 @@
+//! An in-process driver for exercising a [`ModuleHandlers`] implementation
+//! without spinning up a full Ferron server.
+//!
+//! [`TestDriver`] builds fake [`SocketData`]/[`ServerConfiguration`]/
+//! [`ErrorLogger`] values, feeds a request through `request_handler` and
+//! `response_modifying_handler`, and hands back the resulting `ResponseData`
+//! for assertions. The "next module" a handler would normally delegate to is
+//! pluggable via [`TestHandler`], so a test can simulate how the modules
+//! below this one misbehave — forced delays, dropped connections, arbitrary
+//! status codes — by layering the built-in handlers below. No built-in
+//! handler truncates a body; that fault takes a custom `TestHandler`.
+
+use std::error::Error;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use http_body_util::combinators::BoxBody;
+use http_body_util::{BodyExt, Empty, Full};
+use hyper::{Request, Response, StatusCode};
+
+use ferron_common::config::ServerConfiguration;
+use ferron_common::logging::ErrorLogger;
+use ferron_common::modules::{ModuleHandlers, ResponseData, SocketData};
+
+/// Something that can stand in for "the rest of the module chain" in a test.
+///
+/// Implement this to script how the downstream modules behave; the built-in
+/// handlers below cover the common fault-injection cases.
+#[async_trait(?Send)]
+pub trait TestHandler {
+  async fn handle(
+    &mut self,
+    request: Request<BoxBody<Bytes, std::io::Error>>,
+  ) -> Result<ResponseData, Box<dyn Error + Send + Sync>>;
+}
+
+/// Always returns the same response, ignoring the request.
+pub struct StaticResponse {
+  pub status: StatusCode,
+  pub body: Bytes,
+}
+
+impl StaticResponse {
+  pub fn new(status: StatusCode, body: impl Into<Bytes>) -> Self {
+    Self {
+      status,
+      body: body.into(),
+    }
+  }
+}
+
+#[async_trait(?Send)]
+impl TestHandler for StaticResponse {
+  async fn handle(
+    &mut self,
+    _request: Request<BoxBody<Bytes, std::io::Error>>,
+  ) -> Result<ResponseData, Box<dyn Error + Send + Sync>> {
+    Ok(ResponseData {
+      request: None,
+      response: Some(
+        Response::builder()
+          .status(self.status)
+          .body(Full::new(self.body.clone()).map_err(|e| match e {}).boxed())?,
+      ),
+      response_status: None,
+      response_headers: None,
+      new_remote_address: None,
+    })
+  }
+}
+
+/// Waits `duration` before delegating to `inner`, simulating a slow
+/// downstream module.
+pub struct Delay<H> {
+  pub duration: Duration,
+  pub inner: H,
+}
+
+impl<H> Delay<H> {
+  pub fn new(duration: Duration, inner: H) -> Self {
+    Self { duration, inner }
+  }
+}
+
+#[async_trait(?Send)]
+impl<H: TestHandler> TestHandler for Delay<H> {
+  async fn handle(
+    &mut self,
+    request: Request<BoxBody<Bytes, std::io::Error>>,
+  ) -> Result<ResponseData, Box<dyn Error + Send + Sync>> {
+    tokio::time::sleep(self.duration).await;
+    self.inner.handle(request).await
+  }
+}
+
+/// Fails every request with the given error, simulating a dropped
+/// connection or another unrecoverable downstream failure.
+pub struct ErrorHandler {
+  message: String,
+}
+
+impl ErrorHandler {
+  pub fn new(message: impl Into<String>) -> Self {
+    Self { message: message.into() }
+  }
+}
+
+#[async_trait(?Send)]
+impl TestHandler for ErrorHandler {
+  async fn handle(
+    &mut self,
+    _request: Request<BoxBody<Bytes, std::io::Error>>,
+  ) -> Result<ResponseData, Box<dyn Error + Send + Sync>> {
+    Err(std::io::Error::other(self.message.clone()).into())
+  }
+}
+
+/// Cycles through a fixed list of handlers, one per call, repeating the last
+/// one once the list is exhausted. Useful for simulating a downstream module
+/// that behaves differently across retries (e.g. fails once, then succeeds).
+pub struct Sequence {
+  handlers: Vec<Box<dyn TestHandler>>,
+  next: usize,
+}
+
+impl Sequence {
+  /// # Panics
+  ///
+  /// Panics if `handlers` is empty — there would be nothing for `handle` to
+  /// delegate to, on the first call or any other.
+  pub fn new(handlers: Vec<Box<dyn TestHandler>>) -> Self {
+    assert!(!handlers.is_empty(), "Sequence needs at least one handler");
+    Self { handlers, next: 0 }
+  }
+}
+
+#[async_trait(?Send)]
+impl TestHandler for Sequence {
+  async fn handle(
+    &mut self,
+    request: Request<BoxBody<Bytes, std::io::Error>>,
+  ) -> Result<ResponseData, Box<dyn Error + Send + Sync>> {
+    let index = self.next.min(self.handlers.len() - 1);
+    self.next += 1;
+    self.handlers[index].handle(request).await
+  }
+}
+
+/// Drives a [`ModuleHandlers`] implementation through fake server plumbing.
+pub struct TestDriver {
+  config: ServerConfiguration,
+  socket_data: SocketData,
+  error_logger: ErrorLogger,
+}
+
+impl TestDriver {
+  /// Builds a driver with a default fake config, socket, and a logger that
+  /// discards whatever it's given.
+  pub fn new() -> Self {
+    Self {
+      config: ServerConfiguration::default(),
+      socket_data: SocketData {
+        local_addr: SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0),
+        remote_addr: SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0),
+        encrypted: false,
+      },
+      error_logger: ErrorLogger::discarding(),
+    }
+  }
+
+  /// Sends `request` through `handlers.request_handler`. If the module
+  /// passes the request through (`response: None`), delegate to `downstream`
+  /// to produce the response a real next-module would have, then run the
+  /// result back through `handlers.response_modifying_handler`.
+  pub async fn drive(
+    &self,
+    handlers: &mut dyn ModuleHandlers,
+    request: Request<BoxBody<Bytes, std::io::Error>>,
+    downstream: &mut dyn TestHandler,
+  ) -> Result<ResponseData, Box<dyn Error + Send + Sync>> {
+    let first_pass = handlers
+      .request_handler(request, &self.config, &self.socket_data, &self.error_logger)
+      .await?;
+
+    let after_downstream = match first_pass {
+      ResponseData {
+        request: Some(request),
+        response: None,
+        ..
+      } => downstream.handle(request).await?,
+      handled => handled,
+    };
+
+    handlers
+      .response_modifying_handler(after_downstream, &self.config, &self.socket_data, &self.error_logger)
+      .await
+  }
+}
+
+impl Default for TestDriver {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+/// An empty-bodied `GET` request to `path`, ready to hand to [`TestDriver::drive`].
+pub fn get_request(path: &str) -> Request<BoxBody<Bytes, std::io::Error>> {
+  Request::builder()
+    .method("GET")
+    .uri(path)
+    .body(Empty::new().map_err(|e| match e {}).boxed())
+    .expect("a GET request with a valid path always builds")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// A module that never handles anything, so `TestDriver::drive` always
+  /// falls through to the `downstream` handler under test.
+  struct PassThrough;
+
+  #[async_trait(?Send)]
+  impl ModuleHandlers for PassThrough {
+    async fn request_handler(
+      &mut self,
+      request: Request<BoxBody<Bytes, std::io::Error>>,
+      _config: &ServerConfiguration,
+      _socket_data: &SocketData,
+      _error_logger: &ErrorLogger,
+    ) -> Result<ResponseData, Box<dyn Error + Send + Sync>> {
+      Ok(ResponseData {
+        request: Some(request),
+        response: None,
+        response_status: None,
+        response_headers: None,
+        new_remote_address: None,
+      })
+    }
+  }
+
+  async fn body_bytes(response: Response<BoxBody<Bytes, std::io::Error>>) -> Bytes {
+    response.into_body().collect().await.unwrap().to_bytes()
+  }
+
+  #[tokio::test]
+  async fn drive_falls_through_to_downstream() {
+    let driver = TestDriver::new();
+    let mut downstream = StaticResponse::new(StatusCode::OK, "hi");
+
+    let result = driver
+      .drive(&mut PassThrough, get_request("/anything"), &mut downstream)
+      .await
+      .unwrap();
+
+    let response = result.response.expect("StaticResponse always responds");
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_bytes(response).await, Bytes::from_static(b"hi"));
+  }
+
+  #[tokio::test]
+  async fn error_handler_propagates_the_failure() {
+    let driver = TestDriver::new();
+    let mut downstream = ErrorHandler::new("downstream exploded");
+
+    let err = driver
+      .drive(&mut PassThrough, get_request("/anything"), &mut downstream)
+      .await
+      .unwrap_err();
+
+    assert!(err.to_string().contains("downstream exploded"));
+  }
+
+  #[tokio::test]
+  async fn delay_still_delegates_to_its_inner_handler() {
+    let driver = TestDriver::new();
+    let mut downstream = Delay::new(Duration::from_millis(1), StaticResponse::new(StatusCode::NOT_FOUND, ""));
+
+    let result = driver
+      .drive(&mut PassThrough, get_request("/anything"), &mut downstream)
+      .await
+      .unwrap();
+
+    assert_eq!(result.response.unwrap().status(), StatusCode::NOT_FOUND);
+  }
+
+  #[tokio::test]
+  async fn sequence_cycles_then_repeats_the_last_handler() {
+    let mut sequence = Sequence::new(vec![
+      Box::new(StaticResponse::new(StatusCode::OK, "first")),
+      Box::new(StaticResponse::new(StatusCode::IM_A_TEAPOT, "second")),
+    ]);
+
+    let first = sequence.handle(get_request("/a")).await.unwrap();
+    assert_eq!(first.response.unwrap().status(), StatusCode::OK);
+
+    let second = sequence.handle(get_request("/a")).await.unwrap();
+    assert_eq!(second.response.unwrap().status(), StatusCode::IM_A_TEAPOT);
+
+    // Exhausted: repeats the last handler instead of panicking.
+    let third = sequence.handle(get_request("/a")).await.unwrap();
+    assert_eq!(third.response.unwrap().status(), StatusCode::IM_A_TEAPOT);
+  }
+
+  #[test]
+  #[should_panic(expected = "at least one handler")]
+  fn sequence_rejects_an_empty_handler_list() {
+    Sequence::new(vec![]);
+  }
+}